@@ -1,11 +1,41 @@
-pub use bitfield_impl::bitfield;
+pub use bitfield_impl::{bitfield, generate_alignment_markers, BitfieldSpecifier};
 
 pub trait Field {
     const BITS: usize;
+    type Interface;
+
+    fn from_bits(bits: u64) -> Self::Interface;
+    fn into_bits(interface: Self::Interface) -> u64;
 }
 
 bitfield_impl::generate_specifiers!();
 
+#[doc(hidden)]
+pub fn get_bits(data: &[u8], offset: usize, width: usize) -> u64 {
+    let mut value: u64 = 0;
+    for i in 0..width {
+        let bit = offset + i;
+        let byte = data[bit / 8];
+        let set = (byte >> (7 - bit % 8)) & 1;
+        value = (value << 1) | u64::from(set);
+    }
+    value
+}
+
+#[doc(hidden)]
+pub fn set_bits(data: &mut [u8], offset: usize, width: usize, value: u64) {
+    for i in 0..width {
+        let bit = offset + i;
+        let set = (value >> (width - 1 - i)) & 1 == 1;
+        let mask = 1 << (7 - bit % 8);
+        if set {
+            data[bit / 8] |= mask;
+        } else {
+            data[bit / 8] &= !mask;
+        }
+    }
+}
+
 pub type MultipleOfEight<T> = <<T as Array>::Marker as TotalSizeIsMultipleOfEightBits>::Check;
 
 pub enum ZeroMod8 {}
@@ -60,3 +90,30 @@ pub trait TotalSizeIsMultipleOfEightBits {
 impl TotalSizeIsMultipleOfEightBits for ZeroMod8 {
     type Check = ();
 }
+
+// The same trick as `Array`/`TotalSizeIsMultipleOfEightBits` above, indexed
+// by variant count instead of by residue mod 8: every variant count from 1
+// to 256 maps to a marker, but only the markers for counts that are exact
+// powers of two go on to implement `DiscriminantsArePowerOfTwo`. A
+// `#[derive(BitfieldSpecifier)]` enum with a non-power-of-two variant count
+// fails to satisfy that bound, the same way a misaligned `#[bitfield]`
+// struct fails to satisfy `TotalSizeIsMultipleOfEightBits`.
+pub type RequirePowerOfTwoVariants<A> =
+    <<A as VariantCount>::Marker as DiscriminantsArePowerOfTwo>::Check;
+
+pub enum IsPowerOfTwo {}
+pub enum IsNotPowerOfTwo {}
+
+pub trait VariantCount {
+    type Marker;
+}
+
+bitfield_impl::generate_variant_count_markers!();
+
+pub trait DiscriminantsArePowerOfTwo {
+    type Check;
+}
+
+impl DiscriminantsArePowerOfTwo for IsPowerOfTwo {
+    type Check = ();
+}
@@ -2,38 +2,267 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, Data, DeriveInput};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Data, DeriveInput, Ident, LitInt, Token};
+
+/// Arguments of `#[bitfield]` or `#[bitfield(align = N)]`. Bare `#[bitfield]`
+/// is equivalent to `#[bitfield(align = 8)]`.
+struct BitfieldArgs {
+    align: usize,
+}
+
+impl Default for BitfieldArgs {
+    fn default() -> Self {
+        BitfieldArgs { align: 8 }
+    }
+}
+
+impl Parse for BitfieldArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Ok(BitfieldArgs::default());
+        }
+        let key: Ident = input.parse()?;
+        if key != "align" {
+            return Err(syn::Error::new(key.span(), "expected `align`"));
+        }
+        input.parse::<Token![=]>()?;
+        let align: LitInt = input.parse()?;
+        Ok(BitfieldArgs {
+            align: align.base10_parse()?,
+        })
+    }
+}
 
 #[proc_macro_attribute]
-pub fn bitfield(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as BitfieldArgs);
+    let align = args.align;
     let input = parse_macro_input!(input as DeriveInput);
 
+    let vis = &input.vis;
+    let name = &input.ident;
+
     let fields = match &input.data {
-        Data::Struct(data) => data.fields.iter().map(|field| &field.ty),
+        Data::Struct(data) => &data.fields,
         _ => unimplemented!(),
     };
 
+    let field_ident: Vec<_> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let field_ty: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+
+    // The bit offset of each field is the sum of the BITS of every field
+    // that precedes it.
+    let mut field_offset = Vec::with_capacity(field_ty.len());
+    let mut offset = quote!(0usize);
+    for ty in &field_ty {
+        field_offset.push(offset.clone());
+        offset = quote!(#offset + <#ty as bitfield::Field>::BITS);
+    }
+
+    let getter = field_ident
+        .iter()
+        .map(|ident| format_ident!("get_{}", ident));
+    let setter = field_ident
+        .iter()
+        .map(|ident| format_ident!("set_{}", ident));
+
+    let total_bits = quote!(0usize #(+ <#field_ty as bitfield::Field>::BITS)*);
+
+    // `align = 8` reuses the crate's built-in `MultipleOfEight` check so
+    // that the common case expands exactly as it always has. Any other `N`
+    // generates a private, struct-scoped copy of the same machinery sized
+    // for that `N`. Either way this only constrains the total to a multiple
+    // of `N`; `data` below is sized off the total directly, padded up to a
+    // whole number of bytes, so an `N` that isn't itself a multiple of 8
+    // still gets a `data` array large enough for every accessor's bit range.
+    let assertion = if align == 8 {
+        quote! {
+            let _: bitfield::MultipleOfEight<[(); (#total_bits) % 8]>;
+        }
+    } else {
+        let align_mod = format_ident!(
+            "__bitfield_align_{}_of_{}",
+            name.to_string().to_lowercase(),
+            align
+        );
+        quote! {
+            #[doc(hidden)]
+            mod #align_mod {
+                bitfield::generate_alignment_markers!(#align);
+            }
+            let _: #align_mod::MultipleOfN<[(); (#total_bits) % #align]>;
+        }
+    };
+
     TokenStream::from(quote! {
+        #vis struct #name {
+            data: [u8; ((#total_bits) + 7) / 8],
+        }
+
+        impl #name {
+            pub fn new() -> Self {
+                #name {
+                    data: [0u8; ((#total_bits) + 7) / 8],
+                }
+            }
+
+            #(
+                pub fn #getter(&self) -> <#field_ty as bitfield::Field>::Interface {
+                    let bits = bitfield::get_bits(&self.data, #field_offset, <#field_ty as bitfield::Field>::BITS);
+                    <#field_ty as bitfield::Field>::from_bits(bits)
+                }
+
+                pub fn #setter(&mut self, value: <#field_ty as bitfield::Field>::Interface) {
+                    let bits = <#field_ty as bitfield::Field>::into_bits(value);
+                    bitfield::set_bits(
+                        &mut self.data,
+                        #field_offset,
+                        <#field_ty as bitfield::Field>::BITS,
+                        bits,
+                    )
+                }
+            )*
+        }
+
         fn __bitfield() {
-            let _: bitfield::MultipleOfEight<
-                [(); (0 #(+ <#fields as bitfield::Field>::BITS)*) % 8]
-            >;
+            #assertion
         }
     })
 }
 
+/// Generalization of the `ZeroMod8`..`SevenMod8` markers and
+/// `TotalSizeIsMultipleOfEightBits` in the `bitfield` crate to an arbitrary
+/// modulus `N`: emits `N` marker enums `ResidueMod0`..`ResidueModN-1`, an
+/// `Array` impl mapping every `[(); i]` with `0 <= i < N` to its residue
+/// marker, and a single impl of the resulting `Check` trait for
+/// `ResidueMod0`, so that only a remainder of exactly zero typechecks.
+#[proc_macro]
+pub fn generate_alignment_markers(input: TokenStream) -> TokenStream {
+    let n: LitInt = parse_macro_input!(input as LitInt);
+    let n: usize = n.base10_parse().unwrap();
+
+    let residue: Vec<_> = (0..n).map(|i| format_ident!("ResidueMod{}", i)).collect();
+    let index: Vec<_> = (0..n).collect();
+    let zero = &residue[0];
+
+    TokenStream::from(quote! {
+        pub trait Array {
+            type Marker;
+        }
+
+        #(
+            pub enum #residue {}
+
+            impl Array for [(); #index] {
+                type Marker = #residue;
+            }
+        )*
+
+        pub trait Check {
+            type Check;
+        }
+
+        impl Check for #zero {
+            type Check = ();
+        }
+
+        pub type MultipleOfN<T> = <<T as Array>::Marker as Check>::Check;
+    })
+}
+
 #[proc_macro]
 pub fn generate_specifiers(_input: TokenStream) -> TokenStream {
     (0usize..=64usize)
         .map(|width| {
             let name = format_ident!("B{}", width);
+            let interface = match width {
+                0..=8 => quote!(u8),
+                9..=16 => quote!(u16),
+                17..=32 => quote!(u32),
+                _ => quote!(u64),
+            };
             TokenStream::from(quote! {
                 pub enum #name {}
 
                 impl Field for #name {
                     const BITS: usize = #width;
+                    type Interface = #interface;
+
+                    fn from_bits(bits: u64) -> #interface {
+                        bits as #interface
+                    }
+
+                    fn into_bits(interface: #interface) -> u64 {
+                        interface as u64
+                    }
                 }
             })
         })
         .collect()
 }
+
+/// Generates, for every variant count from 1 to 256, an impl of
+/// `VariantCount` mapping that count to `IsPowerOfTwo` or
+/// `IsNotPowerOfTwo` depending on whether it actually is one.
+#[proc_macro]
+pub fn generate_variant_count_markers(_input: TokenStream) -> TokenStream {
+    (1usize..=256usize)
+        .map(|count| {
+            let marker = if count.is_power_of_two() {
+                quote!(IsPowerOfTwo)
+            } else {
+                quote!(IsNotPowerOfTwo)
+            };
+            TokenStream::from(quote! {
+                impl VariantCount for [(); #count] {
+                    type Marker = #marker;
+                }
+            })
+        })
+        .collect()
+}
+
+#[proc_macro_derive(BitfieldSpecifier)]
+pub fn derive_bitfield_specifier(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => unimplemented!(),
+    };
+
+    let count = variants.len();
+    let bits = count.next_power_of_two().trailing_zeros() as usize;
+    let variant_ident: Vec<_> = variants.iter().map(|variant| &variant.ident).collect();
+    let discriminant = 0u64..variant_ident.len() as u64;
+
+    TokenStream::from(quote! {
+        impl bitfield::Field for #name {
+            const BITS: usize = #bits;
+            type Interface = #name;
+
+            fn from_bits(bits: u64) -> #name {
+                match bits {
+                    #(#discriminant => #name::#variant_ident,)*
+                    _ => unreachable!(concat!(stringify!(#name), " discriminant out of range")),
+                }
+            }
+
+            fn into_bits(interface: #name) -> u64 {
+                interface as u64
+            }
+        }
+
+        // Fails to typecheck unless the number of variants of `#name` is
+        // exactly a power of two, which is what makes `BITS` above exact.
+        // Wrapped in an anonymous const so that deriving on more than one
+        // enum in the same module doesn't collide on a shared fn name.
+        const _: () = {
+            fn __bitfield_specifier_variant_count() {
+                let _: bitfield::RequirePowerOfTwoVariants<[(); #count]>;
+            }
+        };
+    })
+}
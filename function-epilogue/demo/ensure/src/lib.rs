@@ -0,0 +1 @@
+pub use ensure_impl::ensure;
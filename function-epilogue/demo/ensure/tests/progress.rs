@@ -0,0 +1,6 @@
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/normal-return.rs");
+    t.pass("tests/ui/panic-unwind.rs");
+}
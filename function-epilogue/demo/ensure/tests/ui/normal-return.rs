@@ -0,0 +1,25 @@
+use ensure::ensure;
+use std::cell::Cell;
+
+thread_local! {
+    static CLEANED_UP: Cell<bool> = Cell::new(false);
+}
+
+struct S(i32);
+
+impl S {
+    #[ensure(CLEANED_UP.with(|cleaned_up| cleaned_up.set(true)))]
+    fn f(&mut self, a: i32, b: i32) -> i32 {
+        self.0 + a + b
+    }
+}
+
+fn main() {
+    let mut s = S(1);
+    let value = s.f(2, 3);
+
+    // The real return value is forwarded even though the cleanup guard
+    // also runs on the way out of the function.
+    assert_eq!(value, 6);
+    assert!(CLEANED_UP.with(|cleaned_up| cleaned_up.get()));
+}
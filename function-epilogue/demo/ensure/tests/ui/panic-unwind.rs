@@ -0,0 +1,25 @@
+use ensure::ensure;
+use std::cell::Cell;
+use std::panic;
+
+thread_local! {
+    static CLEANED_UP: Cell<bool> = Cell::new(false);
+}
+
+struct S;
+
+impl S {
+    #[ensure(CLEANED_UP.with(|cleaned_up| cleaned_up.set(true)))]
+    fn f(&mut self) {
+        panic!("boom");
+    }
+}
+
+fn main() {
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| S.f()));
+
+    // The cleanup guard runs during the unwind even though the body never
+    // reaches its end.
+    assert!(result.is_err());
+    assert!(CLEANED_UP.with(|cleaned_up| cleaned_up.get()));
+}
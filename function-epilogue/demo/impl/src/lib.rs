@@ -0,0 +1,111 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Delimiter, Ident as Ident2, TokenStream as TokenStream2, TokenTree};
+use quote::quote;
+use syn::{parse_macro_input, Expr, FnArg, ItemFn};
+
+/// `#[ensure(cleanup_expr)]` packages the `Guard` scope guard from the
+/// `generated_f` case study as an attribute macro: the annotated function's
+/// body moves into an inner closure (with `self` and its arguments rebound
+/// inside it, exactly like the hand-written version), and `cleanup_expr`
+/// runs via `Drop` when the function exits -- on a normal return, an early
+/// return out of the closure, or an unwinding panic alike. Unlike the case
+/// study, the guard is never `mem::forget`ten.
+#[proc_macro_attribute]
+pub fn ensure(args: TokenStream, input: TokenStream) -> TokenStream {
+    let cleanup = parse_macro_input!(args as Expr);
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = parse_macro_input!(input as ItemFn);
+
+    let rebind = sig.inputs.iter().map(|arg| match arg {
+        FnArg::Receiver(_) => quote!(let _self = self;),
+        FnArg::Typed(pat_type) => {
+            let pat = &pat_type.pat;
+            quote!(let #pat = #pat;)
+        }
+    });
+
+    // `self` is a keyword and cannot be named inside the closure's own
+    // `let` bindings, so occurrences of it in the original body are
+    // rewritten to `_self`, the same way the hand-written case study
+    // does it -- except inside a nested item (impl/fn/mod/trait), whose
+    // own `self` refers to something else entirely.
+    //
+    // The statements are spliced in without the block's own braces: they
+    // already sit inside the closure's braces below, and re-wrapping them
+    // would produce a redundant tail block.
+    let stmts = &block.stmts;
+    let body = rename_self(quote!(#(#stmts)*));
+
+    TokenStream::from(quote! {
+        #(#attrs)* #vis #sig {
+            struct Guard<F: FnMut()>(F);
+
+            impl<F: FnMut()> Drop for Guard<F> {
+                fn drop(&mut self) {
+                    (self.0)();
+                }
+            }
+
+            let _guard = Guard(|| { #cleanup });
+
+            (move || {
+                #(#rebind)*
+                #body
+            })()
+        }
+    })
+}
+
+fn rename_self(tokens: TokenStream2) -> TokenStream2 {
+    let mut out = TokenStream2::new();
+    // Set by an item keyword and held until the item ends at its braced
+    // body or a `;`, so its name, signature and body are all left alone.
+    let mut in_nested_item = false;
+
+    for tt in tokens {
+        match tt {
+            TokenTree::Ident(ident) if ident == "self" && !in_nested_item => {
+                out.extend(std::iter::once(TokenTree::Ident(Ident2::new(
+                    "_self",
+                    ident.span(),
+                ))));
+            }
+            TokenTree::Ident(ref ident)
+                if ident == "impl" || ident == "fn" || ident == "mod" || ident == "trait" =>
+            {
+                out.extend(std::iter::once(tt));
+                in_nested_item = true;
+            }
+            TokenTree::Group(group) => {
+                let mut renamed = proc_macro2::Group::new(
+                    group.delimiter(),
+                    if in_nested_item {
+                        group.stream()
+                    } else {
+                        rename_self(group.stream())
+                    },
+                );
+                renamed.set_span(group.span());
+                out.extend(std::iter::once(TokenTree::Group(renamed)));
+                if group.delimiter() == Delimiter::Brace {
+                    in_nested_item = false;
+                }
+            }
+            TokenTree::Punct(ref punct) if punct.as_char() == ';' => {
+                out.extend(std::iter::once(tt));
+                in_nested_item = false;
+            }
+            other => {
+                out.extend(std::iter::once(other));
+            }
+        }
+    }
+
+    out
+}
@@ -1,3 +1,4 @@
+use ensure::ensure;
 use std::mem;
 
 pub struct S(i32);
@@ -33,6 +34,18 @@ impl S {
         mem::forget(guard);
         value
     }
+
+    // What `generated_f` hand-writes, packaged as a real attribute: "Do the
+    // thing" becomes the attribute's argument, and it runs on every exit
+    // from the function, not only on the panic path that the case study's
+    // guard covers before it is forgotten on normal return.
+    #[ensure(println!("Do the thing"))]
+    pub fn attribute_f(&mut self, a: Arg1, b: Arg2) -> Ret {
+        (&mut self.0, a + b)
+    }
 }
 
-fn main() {}
+fn main() {
+    let mut s = S(0);
+    let _ = s.attribute_f(&1, 2);
+}
@@ -1,4 +1,6 @@
 mod phantom {
+    use std::marker::PhantomData;
+
     pub use self::MyPhantomData::*;
 
     pub enum MyPhantomData<T: ?Sized> {
@@ -13,6 +15,29 @@ mod phantom {
 
     unsafe impl<T: ?Sized + Send> Send for MyPhantomData<T> {}
     unsafe impl<T: ?Sized + Sync> Sync for MyPhantomData<T> {}
+
+    /// A marker for a pure compile-time tag `M`, e.g. the modulus of a
+    /// `ModInt<M>`, that is unconditionally `Send + Sync + Copy` and
+    /// covariant in `M`.
+    ///
+    /// `MyPhantomData<T>` above models "logically stores a `T`", so it is
+    /// only conditionally `Send`/`Sync` and is not `Copy`. `MyTag<M>`
+    /// instead models "tagged by `M`, never actually stores one": it wraps
+    /// `PhantomData<fn() -> M>`, and a function pointer is `Send + Sync +
+    /// Copy` and covariant in its return type no matter what that type is.
+    pub struct MyTag<M>(PhantomData<fn() -> M>);
+
+    impl<M> MyTag<M> {
+        pub const NEW: Self = MyTag(PhantomData);
+    }
+
+    impl<M> Clone for MyTag<M> {
+        fn clone(&self) -> Self {
+            *self
+        }
+    }
+
+    impl<M> Copy for MyTag<M> {}
 }
 
 /// ... documentation illustrating how to use.
@@ -22,6 +47,64 @@ pub type MyPhantomData<T: ?Sized> = phantom::MyPhantomData<T>;
 #[doc(hidden)]
 pub use self::phantom::*;
 
+/// A newtype tagged by a compile-time modulus `M`, demonstrating `MyTag`'s
+/// use: `M` never appears in a field, only in the tag, so `ModInt<M>` stays
+/// `Send + Sync + Copy` and covariant in `M` regardless of what `M` is.
+pub struct ModInt<M> {
+    value: u64,
+    tag: MyTag<M>,
+}
+
+impl<M> ModInt<M> {
+    pub fn new(value: u64) -> Self {
+        ModInt {
+            value,
+            tag: MyTag::NEW,
+        }
+    }
+}
+
+impl<M> Clone for ModInt<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for ModInt<M> {}
+
 fn main() {
     let _: MyPhantomData<usize> = MyPhantomData::<usize>;
+    let _: ModInt<()> = ModInt::new(5);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MyTag;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+    fn assert_copy<T: Copy>() {}
+
+    struct NotSendNotSync(*const ());
+
+    #[test]
+    fn my_tag_is_send_sync_copy_regardless_of_m() {
+        assert_send::<MyTag<NotSendNotSync>>();
+        assert_sync::<MyTag<NotSendNotSync>>();
+        assert_copy::<MyTag<NotSendNotSync>>();
+    }
+
+    #[test]
+    fn my_tag_is_covariant_in_m() {
+        // Only typechecks if `MyTag<&'static str>` is a subtype of
+        // `MyTag<&'short str>`, i.e. if `MyTag` is covariant in its
+        // parameter. A marker over `*mut M` or `Cell<M>` is invariant and
+        // would reject this coercion.
+        fn shorten<'short>(tag: MyTag<&'static str>) -> MyTag<&'short str> {
+            tag
+        }
+
+        let tag: MyTag<&'static str> = MyTag::NEW;
+        let _: MyTag<&str> = shorten(tag);
+    }
 }